@@ -1,10 +1,15 @@
 use colored::*;
+use std::collections::VecDeque;
 use std::env;
+use std::fmt::Write as _;
 use std::fs;
-use std::io::{self, BufRead};
+use std::io::{self, BufRead, Read, Write};
+use std::path::Path;
 use std::process;
+use std::sync::{Arc, Mutex};
+use std::thread;
 use walkdir::WalkDir;
-use regex::Regex;
+use regex::{Regex, RegexBuilder};
 
 /*
     A `Config` struct saves the parsed result.
@@ -19,6 +24,19 @@ struct Config {
     recursive_search: bool,
     print_filenames: bool,
     colored_output: bool,
+    regex_search: bool,
+    json_output: bool,
+    context_before: usize,
+    context_after: usize,
+    include_globs: Vec<String>,
+    exclude_globs: Vec<String>,
+    threads: usize,
+    count_lines_mode: bool,
+    count_matches_mode: bool,
+    show_stats: bool,
+    exec_cmd: Option<String>,
+    force_text: bool,
+    binary_without_match: bool,
 }
 
 impl Config {
@@ -47,24 +65,88 @@ impl Config {
             recursive_search: false,
             print_filenames: false,
             colored_output: false,
+            regex_search: false,
+            json_output: false,
+            context_before: 0,
+            context_after: 0,
+            include_globs: Vec::new(),
+            exclude_globs: Vec::new(),
+            threads: thread::available_parallelism().map(|n| n.get()).unwrap_or(1),
+            count_lines_mode: false,
+            count_matches_mode: false,
+            show_stats: false,
+            exec_cmd: None,
+            force_text: false,
+            binary_without_match: false,
         };
 
-        // Set flags based on `args`:
-        for arg in &args[2..] {
-            match arg.as_str() {
+        // Set flags based on `args`. A plain `for` loop no longer works here
+        // since "-A"/"-B"/"-C" consume the argument that follows them:
+        let mut idx = 2;
+        while idx < args.len() {
+            match args[idx].as_str() {
                 "-i" => config.case_insensitive = true,
                 "-n" => config.print_line_numbers = true,
                 "-v" => config.invert_match = true,
                 "-r" => config.recursive_search = true,
                 "-f" => config.print_filenames = true,
-                "-c" => config.colored_output = true,
+                "--color" => config.colored_output = true,
+                "-e" | "--regex" => config.regex_search = true,
+                "--json" => config.json_output = true,
+                "-c" | "--count" => config.count_lines_mode = true,
+                "--count-matches" => config.count_matches_mode = true,
+                "--stats" => config.show_stats = true,
+                "-x" | "--exec" => {
+                    idx += 1;
+                    let cmd = args.get(idx).ok_or("-x/--exec requires a command argument")?;
+                    config.exec_cmd = Some(cmd.clone());
+                },
+                "--text" => config.force_text = true,
+                "--binary-files=without-match" => config.binary_without_match = true,
+                "-A" => {
+                    idx += 1;
+                    config.context_after = args.get(idx)
+                        .and_then(|n| n.parse().ok())
+                        .ok_or("-A requires a numeric argument")?;
+                },
+                "-B" => {
+                    idx += 1;
+                    config.context_before = args.get(idx)
+                        .and_then(|n| n.parse().ok())
+                        .ok_or("-B requires a numeric argument")?;
+                },
+                "-C" => {
+                    idx += 1;
+                    let n: usize = args.get(idx)
+                        .and_then(|n| n.parse().ok())
+                        .ok_or("-C requires a numeric argument")?;
+                    config.context_before = n;
+                    config.context_after = n;
+                },
+                "--glob" => {
+                    idx += 1;
+                    let pattern = args.get(idx).ok_or("--glob requires a pattern argument")?;
+                    config.include_globs.push(pattern.clone());
+                },
+                "--exclude" => {
+                    idx += 1;
+                    let pattern = args.get(idx).ok_or("--exclude requires a pattern argument")?;
+                    config.exclude_globs.push(pattern.clone());
+                },
+                "--threads" => {
+                    idx += 1;
+                    config.threads = args.get(idx)
+                        .and_then(|n| n.parse().ok())
+                        .ok_or("--threads requires a numeric argument")?;
+                },
                 "-h" | "--help" => {
                     print_help_info();
                     process::exit(0); // exits right away
                 },
                 // Any other arguments will be treated as files / directories:
-                _ => config.target_files.push(arg.clone())
+                arg => config.target_files.push(arg.to_string())
             }
+            idx += 1;
         }
         /*
             NOTE: When the user uses wildcard characters in the filename such
@@ -96,7 +178,22 @@ fn print_help_info() {
     println!("-v                Invert match (exclude lines that match the pattern)");
     println!("-r                Recursive directory search");
     println!("-f                Print filenames");
-    println!("-c                Enable colored output");
+    println!("--color           Enable colored output");
+    println!("-e, --regex       Treat the pattern as a regular expression");
+    println!("--json            Emit one JSON object per matching line");
+    println!("-c, --count       Print only a count of matching lines per file");
+    println!("--count-matches   Print a count of total match occurrences per file");
+    println!("--stats           Print a summary of matched lines/matches/files at the end");
+    println!("-x, --exec CMD    Run CMD per matching line instead of printing it");
+    println!("                  (placeholders: {{}} {{/}} {{.}} {{//}} {{line}} {{linenum}} {{match}})");
+    println!("--text            Treat binary files as text and search them anyway");
+    println!("--binary-files=without-match  Silently skip files that look binary");
+    println!("-A NUM            Print NUM lines of context after each match");
+    println!("-B NUM            Print NUM lines of context before each match");
+    println!("-C NUM            Print NUM lines of context before and after each match");
+    println!("--glob PATTERN    Only recurse into files matching PATTERN (repeatable)");
+    println!("--exclude PATTERN Skip files matching PATTERN during recursion (repeatable)");
+    println!("--threads NUM     Number of worker threads for recursive search (default: logical CPUs)");
     println!("-h, --help        Show help information");
 }
 
@@ -129,74 +226,597 @@ fn execute(config: Config) -> Result<(), Box<dyn std::error::Error>> {
         config.query_str.clone()
     };
 
+    // When "-e"/"--regex" is used, compile the pattern once up front and
+    // reuse it for both the match test and the highlight pass:
+    let regex = if config.regex_search {
+        Some(
+            RegexBuilder::new(&config.query_str)
+                .case_insensitive(config.case_insensitive)
+                .build()?,
+        )
+    } else {
+        None
+    };
+
     // ASSUMPTION: If "-r" is not used, we simply treat it as a file:
     let target_files = if config.recursive_search {
-        collect_files_recursively(&config.target_files)?
+        collect_files_recursively(&config.target_files, &config.include_globs, &config.exclude_globs)?
     } else {
         config.target_files.clone()
     };
 
-    for target_file in target_files {
-        search_in_file(&target_file, &query_str, &config)?;
+    if let Some(exec_cmd) = &config.exec_cmd {
+        let exec_tokens = parse_exec_template(exec_cmd);
+        let mut all_succeeded = true;
+        for target_file in target_files {
+            if !exec_on_matches(&target_file, &query_str, regex.as_ref(), &config, &exec_tokens)? {
+                all_succeeded = false;
+            }
+        }
+        return if all_succeeded {
+            Ok(())
+        } else {
+            Err("one or more --exec invocations exited with a non-zero status".into())
+        };
+    }
+
+    if config.recursive_search && config.threads > 1 && target_files.len() > 1 {
+        search_files_in_parallel(target_files, query_str, regex, config)
+    } else {
+        let mut totals = Totals::default();
+        let mut stdout = io::stdout();
+        for target_file in target_files {
+            let outcome = search_in_file(&target_file, &query_str, regex.as_ref(), &config)?;
+            report_outcome(&target_file, &outcome, &config, &mut stdout)?;
+            totals.add(&outcome);
+        }
+        if config.show_stats {
+            print_stats(&totals);
+        }
+        Ok(())
+    }
+}
+
+/*
+    Feeds `target_files` into a bounded work queue consumed by `config.threads`
+    worker threads. Each worker buffers a whole file's matches into a `String`
+    and flushes it under a mutex, so lines from one file never interleave with
+    another's even though files are searched concurrently.
+*/
+fn search_files_in_parallel(
+    target_files: Vec<String>, query_str: String, regex: Option<Regex>, config: Config
+) -> Result<(), Box<dyn std::error::Error>> {
+    run_parallel_search(target_files, query_str, regex, config, Arc::new(Mutex::new(io::stdout())))
+}
+
+/*
+    Does the actual work for `search_files_in_parallel`, but against any
+    `Write` sink rather than hardcoding real stdout -- so a test can swap in
+    an in-memory buffer and check that the output lock really does keep
+    each worker's file output contiguous. The "--stats" summary is still
+    printed to real stdout, not to `output`.
+*/
+fn run_parallel_search<W: Write + Send + 'static>(
+    target_files: Vec<String>, query_str: String, regex: Option<Regex>, config: Config, output: Arc<Mutex<W>>
+) -> Result<(), Box<dyn std::error::Error>> {
+    let queue = Arc::new(Mutex::new(target_files.into_iter().collect::<VecDeque<String>>()));
+    let query_str = Arc::new(query_str);
+    let regex = Arc::new(regex);
+    let config = Arc::new(config);
+    let errors = Arc::new(Mutex::new(Vec::<String>::new()));
+    let totals = Arc::new(Mutex::new(Totals::default()));
+
+    let worker_count = config.threads.min(queue.lock().unwrap().len().max(1));
+    let mut workers = Vec::with_capacity(worker_count);
+
+    for _ in 0..worker_count {
+        let queue = Arc::clone(&queue);
+        let query_str = Arc::clone(&query_str);
+        let regex = Arc::clone(&regex);
+        let config = Arc::clone(&config);
+        let output = Arc::clone(&output);
+        let errors = Arc::clone(&errors);
+        let totals = Arc::clone(&totals);
+
+        workers.push(thread::spawn(move || {
+            loop {
+                let target_file = match queue.lock().unwrap().pop_front() {
+                    Some(target_file) => target_file,
+                    None => break,
+                };
+
+                match search_in_file(&target_file, &query_str, regex.as_ref().as_ref(), &config) {
+                    Ok(outcome) => {
+                        let mut guard = output.lock().unwrap();
+                        report_outcome(&target_file, &outcome, &config, &mut *guard).expect("writing output failed");
+                        totals.lock().unwrap().add(&outcome);
+                    },
+                    Err(e) => errors.lock().unwrap().push(format!("{}: {}", target_file, e)),
+                }
+            }
+        }));
+    }
+
+    for worker in workers {
+        worker.join().expect("worker thread panicked");
+    }
+
+    if config.show_stats {
+        print_stats(&totals.lock().unwrap());
     }
 
-    Ok(())
+    let errors = Arc::try_unwrap(errors).unwrap().into_inner().unwrap();
+    if errors.is_empty() {
+        Ok(())
+    } else {
+        Err(errors.join("; ").into())
+    }
+}
+
+/*
+    Run-wide totals, summed from each file's `SearchOutcome` and printed by
+    "--stats" ("-c"/"--count-matches" print the per-file `SearchOutcome`).
+*/
+#[derive(Default)]
+struct Totals {
+    matched_lines: usize,
+    total_matches: usize,
+    files_searched: usize,
+    files_with_match: usize,
+}
+
+impl Totals {
+    fn add(&mut self, outcome: &SearchOutcome) {
+        self.matched_lines += outcome.matched_lines;
+        self.total_matches += outcome.total_matches;
+        self.files_searched += 1;
+        if outcome.matched_lines > 0 {
+            self.files_with_match += 1;
+        }
+    }
+}
+
+fn print_stats(totals: &Totals) {
+    println!("{} matched lines", totals.matched_lines);
+    println!("{} matches", totals.total_matches);
+    println!("{} files searched", totals.files_searched);
+    println!("{} files matched", totals.files_with_match);
+}
+
+/*
+    Writes a single file's result to `writer`: the buffered match output in
+    the normal case, or a bare count when "-c"/"--count-matches" suppresses
+    line output. Taking a generic `Write` (rather than printing directly)
+    lets the parallel worker pool flush every file's output atomically
+    under one lock, whatever the sink, and lets tests capture that output
+    instead of the real stdout.
+*/
+fn report_outcome(filename: &str, outcome: &SearchOutcome, config: &Config, writer: &mut impl Write) -> io::Result<()> {
+    if config.count_matches_mode {
+        write_count_line(filename, outcome.total_matches, config, writer)
+    } else if config.count_lines_mode {
+        write_count_line(filename, outcome.matched_lines, config, writer)
+    } else {
+        write!(writer, "{}", outcome.output)
+    }
+}
+
+fn write_count_line(filename: &str, count: usize, config: &Config, writer: &mut impl Write) -> io::Result<()> {
+    if config.print_filenames {
+        writeln!(writer, "{}: {}", filename, count)
+    } else {
+        writeln!(writer, "{}", count)
+    }
 }
 
 /*
     We use the `WalkDir` crate to do the recursive search.
 */
-fn collect_files_recursively(paths: &[String]) -> Result<Vec<String>, io::Error> {
+fn collect_files_recursively(
+    paths: &[String], include_globs: &[String], exclude_globs: &[String]
+) -> Result<Vec<String>, Box<dyn std::error::Error>> {
+    let include_patterns = include_globs
+        .iter()
+        .map(|glob| glob_to_regex(glob))
+        .collect::<Result<Vec<Regex>, regex::Error>>()?;
+    let exclude_patterns = exclude_globs
+        .iter()
+        .map(|glob| glob_to_regex(glob))
+        .collect::<Result<Vec<Regex>, regex::Error>>()?;
+
     let mut files = Vec::new();
     for path in paths {
         for entry in WalkDir::new(path).into_iter().filter_map(|result| result.ok()) {
-            if entry.file_type().is_file() {
-                files.push(entry.path().display().to_string());
+            if !entry.file_type().is_file() {
+                continue;
             }
+
+            let entry_path = entry.path().display().to_string();
+
+            // Exclusion takes precedence over inclusion:
+            if exclude_patterns.iter().any(|pattern| pattern.is_match(&entry_path)) {
+                continue;
+            }
+            if !include_patterns.is_empty()
+                && !include_patterns.iter().any(|pattern| pattern.is_match(&entry_path))
+            {
+                continue;
+            }
+
+            files.push(entry_path);
         }
     }
     Ok(files)
 }
 
 /*
-    We have to `Box` the error since we do not know the error type at compile-time
-    (i.e. not statically determined).
-    https://doc.rust-lang.org/rust-by-example/error/multiple_error_types/boxing_errors.html
+    Translates a shell-style glob pattern into a `Regex` anchored at the end
+    of the path and at either the start of the path or a path-component
+    boundary (a preceding `/`): `\` and `.` are escaped, `*` becomes `.*`,
+    and `?` becomes `.`. The component-boundary anchor means a pattern like
+    "target" followed by a star matches `./target/foo` or
+    `some/dir/target/foo`, not just a path that starts with `target` verbatim.
 */
+fn glob_to_regex(glob: &str) -> Result<Regex, regex::Error> {
+    let mut pattern = String::with_capacity(glob.len() + 8);
+    pattern.push_str("(?:^|/)");
+    for c in glob.chars() {
+        match c {
+            '\\' => pattern.push_str("\\\\"),
+            '.' => pattern.push_str("\\."),
+            '*' => pattern.push_str(".*"),
+            '?' => pattern.push('.'),
+            c => pattern.push(c),
+        }
+    }
+    pattern.push('$');
+    Regex::new(&pattern)
+}
+
+/*
+    A single piece of a "-x"/"--exec" command template: either literal text
+    or a placeholder: fd's path placeholders plus "{line}", "{linenum}"
+    and "{match}".
+*/
+enum ExecToken {
+    Literal(String),
+    FullPath,
+    Basename,
+    NoExt,
+    ParentDir,
+    Line,
+    LineNum,
+    Match,
+}
+
+/*
+    Matches a placeholder at the start of `rest`. No placeholder is a prefix
+    of another, so the order of these checks does not change the result:
+*/
+fn match_placeholder(rest: &str) -> Option<(&'static str, ExecToken)> {
+    if rest.starts_with("{//}") {
+        Some(("{//}", ExecToken::ParentDir))
+    } else if rest.starts_with("{.}") {
+        Some(("{.}", ExecToken::NoExt))
+    } else if rest.starts_with("{/}") {
+        Some(("{/}", ExecToken::Basename))
+    } else if rest.starts_with("{linenum}") {
+        Some(("{linenum}", ExecToken::LineNum))
+    } else if rest.starts_with("{line}") {
+        Some(("{line}", ExecToken::Line))
+    } else if rest.starts_with("{match}") {
+        Some(("{match}", ExecToken::Match))
+    } else if rest.starts_with("{}") {
+        Some(("{}", ExecToken::FullPath))
+    } else {
+        None
+    }
+}
+
+/*
+    Parses a single whitespace-separated word of a "-x"/"--exec" template
+    into a mix of literal text and placeholders.
+*/
+fn parse_exec_word(word: &str) -> Vec<ExecToken> {
+    let mut tokens = Vec::new();
+    let mut literal = String::new();
+    let mut rest = word;
+
+    while !rest.is_empty() {
+        if let Some((placeholder, token)) = match_placeholder(rest) {
+            if !literal.is_empty() {
+                tokens.push(ExecToken::Literal(std::mem::take(&mut literal)));
+            }
+            tokens.push(token);
+            rest = &rest[placeholder.len()..];
+        } else {
+            let mut chars = rest.chars();
+            literal.push(chars.next().unwrap());
+            rest = chars.as_str();
+        }
+    }
+    if !literal.is_empty() {
+        tokens.push(ExecToken::Literal(literal));
+    }
+
+    tokens
+}
+
+/*
+    Parses a "-x"/"--exec" template once, so per-match substitution is just a
+    walk over the token list rather than repeated string searching. The
+    template is split into argv words up front -- like fd, we never hand the
+    expanded command to a shell, so matched text can't inject extra commands.
+*/
+fn parse_exec_template(template: &str) -> Vec<Vec<ExecToken>> {
+    template.split_whitespace().map(parse_exec_word).collect()
+}
+
+/*
+    Substitutes a parsed "-x"/"--exec" template for a single matching line,
+    producing the argv vector to run directly (no shell involved).
+*/
+fn expand_exec_template(
+    tokens: &[Vec<ExecToken>], filename: &str, line_number: usize, line: &str, match_text: &str
+) -> Vec<String> {
+    let path = Path::new(filename);
+
+    tokens
+        .iter()
+        .map(|word| {
+            let mut arg = String::new();
+            for token in word {
+                match token {
+                    ExecToken::Literal(text) => arg.push_str(text),
+                    ExecToken::FullPath => arg.push_str(filename),
+                    ExecToken::Basename => arg.push_str(&path.file_name().map(|s| s.to_string_lossy()).unwrap_or_default()),
+                    ExecToken::NoExt => arg.push_str(&path.with_extension("").display().to_string()),
+                    ExecToken::ParentDir => arg.push_str(&path.parent().map(|p| p.display().to_string()).unwrap_or_default()),
+                    ExecToken::Line => arg.push_str(line),
+                    ExecToken::LineNum => arg.push_str(&line_number.to_string()),
+                    ExecToken::Match => arg.push_str(match_text),
+                }
+            }
+            arg
+        })
+        .collect()
+}
+
+/*
+    Runs every matching line in `filename` through the "-x"/"--exec" template,
+    returning whether every invocation exited successfully.
+*/
+fn exec_on_matches(
+    filename: &str, query_str: &str, regex: Option<&Regex>, config: &Config, exec_tokens: &[Vec<ExecToken>]
+) -> Result<bool, Box<dyn std::error::Error>> {
+    let file = fs::File::open(filename)?;
+    let reader = io::BufReader::new(file);
+    let mut all_succeeded = true;
+
+    for (idx, line) in reader.lines().enumerate() {
+        let line_result = line?;
+
+        let matched = if let Some(regex) = regex {
+            regex.is_match(&line_result)
+        } else if config.case_insensitive {
+            line_result.to_lowercase().contains(query_str)
+        } else {
+            line_result.contains(query_str)
+        };
+
+        let should_run = if config.invert_match { !matched } else { matched };
+        if !should_run {
+            continue;
+        }
+
+        let match_text = find_submatches(&line_result, &config.query_str, regex, config.case_insensitive)
+            .into_iter()
+            .next()
+            .map(|(_, _, matched_text)| matched_text)
+            .unwrap_or_default();
+
+        let argv = expand_exec_template(exec_tokens, filename, idx + 1, &line_result, &match_text);
+        if !run_exec_command(&argv)? {
+            all_succeeded = false;
+        }
+    }
+
+    Ok(all_succeeded)
+}
+
+/*
+    Runs a templated command's argv directly -- no shell is involved, so
+    matched text or filenames containing shell metacharacters can't inject
+    extra commands -- forwarding its stdout directly but draining stderr on
+    a background thread so a process producing lots of stderr output can't
+    deadlock the pipe.
+*/
+fn run_exec_command(argv: &[String]) -> io::Result<bool> {
+    let Some((program, args)) = argv.split_first() else {
+        return Err(io::Error::new(io::ErrorKind::InvalidInput, "--exec command is empty"));
+    };
+
+    let mut child = process::Command::new(program)
+        .args(args)
+        .stdout(process::Stdio::inherit())
+        .stderr(process::Stdio::piped())
+        .spawn()?;
+
+    let mut stderr = child.stderr.take().expect("stderr was piped");
+    let stderr_reader = thread::spawn(move || {
+        let mut buf = Vec::new();
+        let _ = stderr.read_to_end(&mut buf);
+        buf
+    });
+
+    let status = child.wait()?;
+    let stderr_output = stderr_reader.join().unwrap_or_default();
+    io::stderr().write_all(&stderr_output)?;
+
+    Ok(status.success())
+}
+
+/*
+    The result of searching a single file: the buffered, human/JSON-formatted
+    output (not printed in count mode, since line output is suppressed there) plus
+    the tallies that back "-c"/"--count-matches"/"--stats".
+*/
+struct SearchOutcome {
+    output: String,
+    matched_lines: usize,
+    total_matches: usize,
+}
+
 fn search_in_file(
-    filename: &str, query_str: &str, config: &Config
-) -> Result<(), Box<dyn std::error::Error>> {
+    filename: &str, query_str: &str, regex: Option<&Regex>, config: &Config
+) -> Result<SearchOutcome, Box<dyn std::error::Error>> {
     /*
         We use the `?` operator to match the `Result` with `Ok()` and `Err()`.
         https://doc.rust-lang.org/rust-by-example/std/result/question_mark.html
         If the "-r" flag is not used, we may end up opening a directory.
     */
     let file = fs::File::open(filename)?;
-    let reader = io::BufReader::new(file);
+    let mut reader = io::BufReader::new(file);
 
-    // Look for matches line by line and print as needed:
-    for (idx, line) in reader.lines().enumerate() {
-        let line_result = line?; // propagates errors
+    // Count mode suppresses normal line output entirely; only the tallies matter:
+    let count_mode = config.count_lines_mode || config.count_matches_mode;
+
+    // Matches are buffered into a single `String` rather than printed
+    // directly, so a worker thread can flush a whole file's output atomically:
+    let mut output = String::new();
+    let mut matched_lines = 0usize;
+    let mut total_matches = 0usize;
 
-        let matched = if config.case_insensitive {
+    // Ring buffer holding up to `context_before` not-yet-printed lines, used
+    // to backfill "before" context once a match is found:
+    let mut before_buffer: VecDeque<(usize, String)> = VecDeque::with_capacity(config.context_before);
+    // Counts down the "after" context lines still owed following a match:
+    let mut after_remaining = 0usize;
+    // Index of the last line we printed, used to detect when two matches'
+    // context blocks are contiguous (no "--" separator needed) or not:
+    let mut last_printed_idx: Option<usize> = None;
+
+    // Read raw bytes rather than `BufRead::lines()` so a NUL byte can be
+    // detected before it would otherwise surface as a UTF-8 decode error:
+    let mut raw_line = Vec::new();
+    let mut idx = 0usize;
+    loop {
+        raw_line.clear();
+        if reader.read_until(b'\n', &mut raw_line)? == 0 {
+            break; // EOF
+        }
+        if raw_line.last() == Some(&b'\n') {
+            raw_line.pop();
+            if raw_line.last() == Some(&b'\r') {
+                raw_line.pop();
+            }
+        }
+
+        if !config.force_text && raw_line.contains(&0) {
+            if config.binary_without_match {
+                // The whole file is treated as a non-match, not just the
+                // NUL-containing tail -- any tallies from text read before
+                // the NUL must not leak into "-c"/"--count-matches"/"--stats":
+                return Ok(SearchOutcome { output: String::new(), matched_lines: 0, total_matches: 0 });
+            }
+            if matched_lines > 0 {
+                writeln!(output, "Binary file {} matches", filename)?;
+            }
+            break;
+        }
+
+        let line_result = String::from_utf8_lossy(&raw_line).into_owned();
+
+        let matched = if let Some(regex) = regex {
+            regex.is_match(&line_result)
+        } else if config.case_insensitive {
             line_result.to_lowercase().contains(query_str)
         } else {
             line_result.contains(query_str)
         };
 
         let should_print = if config.invert_match { !matched } else { matched };
+
         if should_print {
-            print_match(idx, &line_result, filename, config);
+            matched_lines += 1;
+            total_matches += find_submatches(&line_result, &config.query_str, regex, config.case_insensitive).len();
+
+            if count_mode {
+                continue;
+            }
+
+            // Only -A/-B/-C enable context bookkeeping; with no context
+            // requested, matches never get a "--" separator between them:
+            if config.context_before > 0 || config.context_after > 0 {
+                // The earliest line about to be printed is either the start of
+                // the buffered "before" context, or this match itself:
+                let first_new_idx = idx.saturating_sub(before_buffer.len());
+                if let Some(last_idx) = last_printed_idx {
+                    if first_new_idx > last_idx + 1 {
+                        writeln!(output, "--")?;
+                    }
+                }
+
+                for (ctx_idx, ctx_line) in before_buffer.drain(..) {
+                    append_context_line(&mut output, ctx_idx, &ctx_line, filename, config)?;
+                }
+            }
+
+            append_match(&mut output, idx, &line_result, filename, regex, config)?;
+            last_printed_idx = Some(idx);
+            after_remaining = config.context_after;
+        } else if count_mode {
+            continue;
+        } else if after_remaining > 0 {
+            append_context_line(&mut output, idx, &line_result, filename, config)?;
+            last_printed_idx = Some(idx);
+            after_remaining -= 1;
+        } else if config.context_before > 0 {
+            if before_buffer.len() == config.context_before {
+                before_buffer.pop_front();
+            }
+            before_buffer.push_back((idx, line_result));
         }
+
+        idx += 1;
     }
 
-    Ok(())
+    Ok(SearchOutcome { output, matched_lines, total_matches })
+}
+
+/*
+    Appends a context line ("-A"/"-B"/"-C") with the same filename/line-number
+    prefixes as a match, but without highlighting, matching grep behavior.
+*/
+fn append_context_line(
+    output: &mut String, line_idx: usize, line: &str, filename: &str, config: &Config
+) -> std::fmt::Result {
+    if config.print_filenames && config.print_line_numbers {
+        writeln!(output, "{}: {}: {}", filename, line_idx + 1, line)
+    } else if config.print_filenames {
+        writeln!(output, "{}: {}", filename, line)
+    } else if config.print_line_numbers {
+        writeln!(output, "{}: {}", line_idx + 1, line)
+    } else {
+        writeln!(output, "{}", line)
+    }
 }
 
-fn print_match(line_idx: usize, line: &str, filename: &str, config: &Config) {
+fn append_match(
+    output: &mut String, line_idx: usize, line: &str, filename: &str, regex: Option<&Regex>, config: &Config
+) -> std::fmt::Result {
+    if config.json_output {
+        return append_match_json(output, line_idx, line, filename, regex, config);
+    }
+
     // Highlight the query string in a given line:
     let formatted_line = if config.colored_output {
-        if config.case_insensitive {
+        if let Some(regex) = regex {
+            // Highlight every match found by the compiled regex:
+            regex.replace_all(line, |caps: &regex::Captures| {
+                caps[0].red().to_string()
+            }).to_string()
+        } else if config.case_insensitive {
             // Highlight exact matches and case-insensitive matches:
             let regex_pattern = Regex::new(
                 &format!("(?i){}",
@@ -215,15 +835,303 @@ fn print_match(line_idx: usize, line: &str, filename: &str, config: &Config) {
 
     if config.print_filenames && config.print_line_numbers {
         // Print both filename and line number:
-        println!("{}: {}: {}", filename, line_idx + 1, formatted_line);
+        writeln!(output, "{}: {}: {}", filename, line_idx + 1, formatted_line)
     } else if config.print_filenames {
         // Print filename only:
-        println!("{}: {}", filename, formatted_line);
+        writeln!(output, "{}: {}", filename, formatted_line)
     } else if config.print_line_numbers {
         // Print line number only:
-        println!("{}: {}", line_idx + 1, formatted_line);
+        writeln!(output, "{}: {}", line_idx + 1, formatted_line)
     } else {
         // Just print the line itself:
-        println!("{}", formatted_line);
+        writeln!(output, "{}", formatted_line)
+    }
+}
+
+/*
+    Emits a single JSON-Lines object for a matching line, mirroring
+    ripgrep's `--json` "match" message.
+*/
+fn append_match_json(
+    output: &mut String, line_idx: usize, line: &str, filename: &str, regex: Option<&Regex>, config: &Config
+) -> std::fmt::Result {
+    let submatches = find_submatches(line, &config.query_str, regex, config.case_insensitive);
+
+    let submatches_json = submatches
+        .iter()
+        .map(|(start, end, matched)| {
+            format!(
+                "{{\"match\":\"{}\",\"start\":{},\"end\":{}}}",
+                escape_json_string(matched), start, end
+            )
+        })
+        .collect::<Vec<String>>()
+        .join(",");
+
+    writeln!(
+        output,
+        "{{\"type\":\"match\",\"path\":\"{}\",\"line_number\":{},\"lines\":\"{}\",\"submatches\":[{}]}}",
+        escape_json_string(filename), line_idx + 1, escape_json_string(line), submatches_json
+    )
+}
+
+/*
+    Finds every occurrence of the query within a line, returning
+    (start_byte, end_byte, matched_text) triples used for JSON submatches.
+*/
+fn find_submatches(
+    line: &str, query_str: &str, regex: Option<&Regex>, case_insensitive: bool
+) -> Vec<(usize, usize, String)> {
+    if let Some(regex) = regex {
+        regex
+            .find_iter(line)
+            .map(|m| (m.start(), m.end(), m.as_str().to_string()))
+            .collect()
+    } else if case_insensitive {
+        // Lowercasing can change a char's byte length (e.g. Turkish 'İ'),
+        // so we can't reuse offsets from a lowercased copy to slice the
+        // original `line` -- that can land mid-codepoint and panic. Instead
+        // we walk `line`'s own char boundaries and fold case per-char.
+        let lowercase_query = query_str.to_lowercase();
+        let query_char_count = lowercase_query.chars().count();
+        let chars: Vec<(usize, char)> = line.char_indices().collect();
+        let mut matches = Vec::new();
+        for start in 0..chars.len() {
+            let mut folded = String::new();
+            let mut end = start;
+            while folded.chars().count() < query_char_count && end < chars.len() {
+                folded.extend(chars[end].1.to_lowercase());
+                end += 1;
+            }
+            if folded == lowercase_query {
+                let start_byte = chars[start].0;
+                let end_byte = chars.get(end).map_or(line.len(), |(byte, _)| *byte);
+                matches.push((start_byte, end_byte, line[start_byte..end_byte].to_string()));
+            }
+        }
+        matches
+    } else {
+        line.match_indices(query_str)
+            .map(|(start, matched)| (start, start + matched.len(), matched.to_string()))
+            .collect()
+    }
+}
+
+/*
+    Escapes a string for safe embedding inside a JSON string literal.
+*/
+fn escape_json_string(input: &str) -> String {
+    let mut escaped = String::with_capacity(input.len());
+    for c in input.chars() {
+        match c {
+            '"' => escaped.push_str("\\\""),
+            '\\' => escaped.push_str("\\\\"),
+            '\n' => escaped.push_str("\\n"),
+            '\r' => escaped.push_str("\\r"),
+            '\t' => escaped.push_str("\\t"),
+            c if (c as u32) < 0x20 => escaped.push_str(&format!("\\u{:04x}", c as u32)),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    /* A `Config` with line numbers on, a single thread, and every other
+    flag at its default, for tests to override via struct-update syntax. */
+    fn test_config(query: &str) -> Config {
+        Config {
+            query_str: query.to_string(),
+            target_files: Vec::new(),
+            case_insensitive: false,
+            print_line_numbers: true,
+            invert_match: false,
+            recursive_search: false,
+            print_filenames: false,
+            colored_output: false,
+            regex_search: false,
+            json_output: false,
+            context_before: 0,
+            context_after: 0,
+            include_globs: Vec::new(),
+            exclude_globs: Vec::new(),
+            threads: 1,
+            count_lines_mode: false,
+            count_matches_mode: false,
+            show_stats: false,
+            exec_cmd: None,
+            force_text: false,
+            binary_without_match: false,
+        }
+    }
+
+    /* Writes `contents` to a uniquely-named file under the OS temp dir so
+    concurrently-run tests never collide, returning its path. */
+    fn write_temp_file(label: &str, contents: &str) -> std::path::PathBuf {
+        static COUNTER: AtomicUsize = AtomicUsize::new(0);
+        let id = COUNTER.fetch_add(1, Ordering::SeqCst);
+        let path = std::env::temp_dir().join(format!("rust_grep_plus_test_{}_{}_{}.txt", process::id(), label, id));
+        fs::write(&path, contents).unwrap();
+        path
+    }
+
+    #[test]
+    fn parallel_search_keeps_each_files_output_contiguous() {
+        // Several files, each with multiple matching lines distinct enough
+        // to detect interleaving if the output-lock guard around
+        // `report_outcome` were ever dropped or moved.
+        let paths: Vec<std::path::PathBuf> = (0..6)
+            .map(|file_idx| {
+                let lines: String = (0..20)
+                    .map(|line_idx| format!("foo file{} line{}\n", file_idx, line_idx))
+                    .collect();
+                write_temp_file(&format!("parallel_{}", file_idx), &lines)
+            })
+            .collect();
+        let target_files: Vec<String> = paths.iter().map(|p| p.to_str().unwrap().to_string()).collect();
+
+        let mut config = test_config("foo");
+        config.threads = 4;
+        config.target_files = target_files.clone();
+
+        let sink: Arc<Mutex<Vec<u8>>> = Arc::new(Mutex::new(Vec::new()));
+        run_parallel_search(target_files.clone(), "foo".to_string(), None, config, Arc::clone(&sink))
+            .unwrap();
+
+        for path in &paths {
+            fs::remove_file(path).unwrap();
+        }
+
+        let captured = String::from_utf8(Arc::try_unwrap(sink).unwrap().into_inner().unwrap()).unwrap();
+        let all_lines: Vec<&str> = captured.lines().collect();
+
+        // Each file's 20 lines must appear back-to-back somewhere in the
+        // captured output -- never split up by another file's lines:
+        for file_idx in 0..target_files.len() {
+            let marker = format!("file{} line", file_idx);
+            let positions: Vec<usize> = all_lines
+                .iter()
+                .enumerate()
+                .filter(|(_, line)| line.contains(&marker))
+                .map(|(idx, _)| idx)
+                .collect();
+            assert_eq!(positions.len(), 20, "expected all 20 lines for file {}", file_idx);
+            let first = positions[0];
+            let contiguous: Vec<usize> = (first..first + 20).collect();
+            assert_eq!(positions, contiguous, "file {}'s lines were interleaved with another file's", file_idx);
+        }
+    }
+
+    #[test]
+    fn no_separator_between_matches_without_context() {
+        let path = write_temp_file("no_sep", "foo 1\nbar\nbar\nbar\nfoo 2\n");
+        let config = test_config("foo");
+        let outcome = search_in_file(path.to_str().unwrap(), "foo", None, &config).unwrap();
+        fs::remove_file(&path).unwrap();
+
+        assert_eq!(outcome.matched_lines, 2);
+        assert!(!outcome.output.contains("--"), "plain search should never print a -- separator");
+    }
+
+    #[test]
+    fn separator_between_non_contiguous_context_blocks() {
+        let path = write_temp_file("sep", "foo 1\nbar\nbar\nbar\nfoo 2\n");
+        let config = Config { context_before: 1, context_after: 1, ..test_config("foo") };
+        let outcome = search_in_file(path.to_str().unwrap(), "foo", None, &config).unwrap();
+        fs::remove_file(&path).unwrap();
+
+        assert!(outcome.output.contains("--"), "gap between context blocks should print a -- separator");
+    }
+
+    #[test]
+    fn no_separator_when_context_windows_overlap() {
+        let path = write_temp_file("overlap", "foo 1\nbar\nfoo 2\n");
+        let config = Config { context_before: 1, context_after: 1, ..test_config("foo") };
+        let outcome = search_in_file(path.to_str().unwrap(), "foo", None, &config).unwrap();
+        fs::remove_file(&path).unwrap();
+
+        assert!(!outcome.output.contains("--"), "overlapping context windows should merge without a separator");
+    }
+
+    #[test]
+    fn expand_exec_template_keeps_substituted_text_as_one_argv_word() {
+        let tokens = parse_exec_template("echo {line} {match} -- {}");
+        let argv = expand_exec_template(&tokens, "some/dir/file.rs", 3, "a line; rm -rf /", "match;ing");
+
+        // Each placeholder's substitution stays a single argv element, even
+        // though it contains shell metacharacters -- there's no shell here
+        // to split or reinterpret them:
+        assert_eq!(
+            argv,
+            vec![
+                "echo".to_string(),
+                "a line; rm -rf /".to_string(),
+                "match;ing".to_string(),
+                "--".to_string(),
+                "some/dir/file.rs".to_string(),
+            ]
+        );
+    }
+
+    #[test]
+    fn exec_treats_matched_text_as_inert_argv_not_shell_input() {
+        // Get a unique path, but make sure it doesn't exist yet -- it's
+        // just the injection target for the "$(touch ...)" below:
+        let marker = write_temp_file("exec_injection_marker", "");
+        fs::remove_file(&marker).unwrap();
+
+        let line = format!("foo $(touch {})", marker.display());
+        let path = write_temp_file("exec_injection_source", &format!("{}\n", line));
+
+        let exec_tokens = parse_exec_template("echo {line}");
+        let config = test_config("foo");
+        exec_on_matches(path.to_str().unwrap(), "foo", None, &config, &exec_tokens).unwrap();
+
+        fs::remove_file(&path).unwrap();
+
+        assert!(!marker.exists(), "matched text containing shell metacharacters must not be run by a shell");
+    }
+
+    #[test]
+    fn glob_to_regex_matches_at_any_path_component_boundary() {
+        let exclude = glob_to_regex("target/*").unwrap();
+        assert!(exclude.is_match("./target/foo.rs"));
+        assert!(exclude.is_match("some/dir/target/foo.rs"));
+        assert!(exclude.is_match("target/foo.rs"));
+        assert!(!exclude.is_match("not-target/foo.rs"));
+    }
+
+    #[test]
+    fn glob_to_regex_star_matches_any_directory_depth() {
+        let include = glob_to_regex("*.rs").unwrap();
+        assert!(include.is_match("src/main.rs"));
+        assert!(include.is_match("./main.rs"));
+        assert!(!include.is_match("main.rs.bak"));
+    }
+
+    #[test]
+    fn find_submatches_plain_case_sensitive() {
+        let results = find_submatches("foo bar foo", "foo", None, false);
+        assert_eq!(results, vec![(0, 3, "foo".to_string()), (8, 11, "foo".to_string())]);
+    }
+
+    #[test]
+    fn find_submatches_case_insensitive_ascii() {
+        let results = find_submatches("Foo BAR foo", "foo", None, true);
+        assert_eq!(results, vec![(0, 3, "Foo".to_string()), (8, 11, "foo".to_string())]);
+    }
+
+    #[test]
+    fn find_submatches_case_insensitive_no_panic_on_expanding_lowercase() {
+        // Regression test: lowercasing can change a char's byte length (e.g.
+        // Turkish 'İ' U+0130, 2 bytes -> 3-byte "i̇"), so offsets from a lowercased
+        // copy must not be used to slice the original string, or this
+        // panics with "byte index is not a char boundary".
+        let results = find_submatches("İstanbul", "i", None, true);
+        assert!(results.is_empty());
     }
 }